@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A binary or bitstring value, as produced by Erlang's `<<...>>` syntax.
+///
+/// Unlike a plain byte buffer, the number of valid bits does not need to be a
+/// multiple of 8 -- matching BEAM's notion of a "bitstring" where a binary is
+/// just the special case that happens to be byte-aligned. `data` always holds
+/// whole bytes, and `trailing_bits` says how many bits of the last byte are
+/// actually part of the value (8 when the bitstring is byte-aligned, and thus
+/// a proper binary).
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Default)]
+#[repr(C)]
+pub struct Binary {
+    data: Vec<u8>,
+    trailing_bits: u8,
+}
+
+impl Binary {
+    pub fn new() -> Binary {
+        Binary {
+            data: vec![],
+            trailing_bits: 8,
+        }
+    }
+
+    pub fn with_capacity(bytes: usize) -> Binary {
+        Binary {
+            data: Vec::with_capacity(bytes),
+            trailing_bits: 8,
+        }
+    }
+
+    /// Total number of valid bits held in this value.
+    pub fn bit_len(&self) -> usize {
+        match self.data.len() {
+            0 => 0,
+            len => (len - 1) * 8 + self.trailing_bits as usize,
+        }
+    }
+
+    /// Whether this bitstring happens to be byte-aligned, i.e. a proper binary.
+    pub fn is_binary(&self) -> bool {
+        self.trailing_bits == 8
+    }
+
+    /// Append `width` bits of `value`, growing the buffer as needed.
+    ///
+    /// `big_endian` picks *byte* order, not bit order within a byte: for a
+    /// byte-aligned `width`, little-endian emits the least-significant byte
+    /// first (each byte still written MSB-first), so a 16-bit `0x0102`
+    /// serializes to `02 01`, matching `<<0x0102:16/little>>`. For a `width`
+    /// that isn't a multiple of 8 there is no whole byte to reorder, so bits
+    /// are written MSB-first regardless of endianness.
+    ///
+    /// `width` is capped at 64 bits, because the segment round-trips through
+    /// a `u64` here; copying a wider (or arbitrary-width) segment verbatim
+    /// from another `Binary` -- e.g. `BsPutBinary`/`BsGetBinary` -- must go
+    /// through `append_bits_from` instead, which copies bit-by-bit and never
+    /// materializes an integer.
+    pub fn push_bits(&mut self, value: u64, width: u32, big_endian: bool) {
+        assert!(width <= 64, "push_bits only supports segments up to 64 bits wide");
+        if big_endian || width % 8 != 0 {
+            for i in 0..width {
+                let shift = width - 1 - i;
+                self.push_bit((value >> shift) & 1 == 1);
+            }
+            return;
+        }
+        let byte_count = width / 8;
+        for i in 0..byte_count {
+            let byte = (value >> (i * 8)) & 0xFF;
+            for bit in (0..8).rev() {
+                self.push_bit((byte >> bit) & 1 == 1);
+            }
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        if self.trailing_bits == 8 || self.data.is_empty() {
+            self.data.push(0);
+            self.trailing_bits = 0;
+        }
+        if bit {
+            let last = self.data.last_mut().unwrap();
+            *last |= 1 << (7 - self.trailing_bits);
+        }
+        self.trailing_bits += 1;
+    }
+
+    /// Read `width` bits starting at `(byte_offset, bit_offset)`, returning
+    /// the decoded unsigned value and the cursor position just past the read
+    /// bits. Returns `None` if fewer than `width` bits remain. See
+    /// `push_bits` for what `big_endian` means for a byte-aligned `width`, and
+    /// for why `width` is capped at 64 -- use `append_bits_from` to copy a
+    /// wider segment verbatim into another `Binary`.
+    pub fn read_bits(
+        &self,
+        byte_offset: usize,
+        bit_offset: u8,
+        width: u32,
+        big_endian: bool,
+    ) -> Option<(u64, usize, u8)> {
+        assert!(width <= 64, "read_bits only supports segments up to 64 bits wide");
+        if byte_offset * 8 + bit_offset as usize + width as usize > self.bit_len() {
+            return None;
+        }
+        let mut byte = byte_offset;
+        let mut bit = bit_offset;
+        let mut read_byte = |byte: &mut usize, bit: &mut u8| -> u8 {
+            let mut value = 0u8;
+            for _ in 0..8 {
+                let b = (self.data[*byte] >> (7 - *bit)) & 1;
+                value = (value << 1) | b;
+                *bit += 1;
+                if *bit == 8 {
+                    *bit = 0;
+                    *byte += 1;
+                }
+            }
+            value
+        };
+
+        if big_endian || width % 8 != 0 {
+            let mut value: u64 = 0;
+            for _ in 0..width {
+                let b = (self.data[byte] >> (7 - bit)) & 1;
+                value = (value << 1) | b as u64;
+                bit += 1;
+                if bit == 8 {
+                    bit = 0;
+                    byte += 1;
+                }
+            }
+            return Some((value, byte, bit));
+        }
+
+        let byte_count = width / 8;
+        let mut value: u64 = 0;
+        for i in 0..byte_count {
+            let b = read_byte(&mut byte, &mut bit);
+            value |= (b as u64) << (i * 8);
+        }
+        Some((value, byte, bit))
+    }
+
+    /// Like `read_bits`, but sign-extends the result based on the high bit
+    /// of the `width`-bit value, for `BsGetInteger { flags: { signed: true
+    /// } }`.
+    pub fn read_signed_bits(
+        &self,
+        byte_offset: usize,
+        bit_offset: u8,
+        width: u32,
+        big_endian: bool,
+    ) -> Option<(i64, usize, u8)> {
+        let (value, byte, bit) = self.read_bits(byte_offset, bit_offset, width, big_endian)?;
+        Some((sign_extend(value, width), byte, bit))
+    }
+
+    fn bit_at(&self, byte_offset: usize, bit_offset: u8) -> bool {
+        (self.data[byte_offset] >> (7 - bit_offset)) & 1 == 1
+    }
+
+    /// Append `width` bits copied verbatim from `source` starting at
+    /// `(byte_offset, bit_offset)`, one bit at a time. Unlike going through
+    /// `read_bits` + `push_bits`, this never materializes the segment as a
+    /// `u64`, so it copies segments of any width correctly instead of
+    /// silently truncating anything over 64 bits -- the case that matters
+    /// for `BsPutBinary`/`BsGetBinary`, which copy whole binaries rather than
+    /// fixed-size integers. Returns `None` if fewer than `width` bits remain
+    /// in `source` from that position.
+    pub fn append_bits_from(
+        &mut self,
+        source: &Binary,
+        byte_offset: usize,
+        bit_offset: u8,
+        width: u32,
+    ) -> Option<()> {
+        if byte_offset * 8 + bit_offset as usize + width as usize > source.bit_len() {
+            return None;
+        }
+        let mut byte = byte_offset;
+        let mut bit = bit_offset;
+        for _ in 0..width {
+            self.push_bit(source.bit_at(byte, bit));
+            bit += 1;
+            if bit == 8 {
+                bit = 0;
+                byte += 1;
+            }
+        }
+        Some(())
+    }
+}
+
+fn sign_extend(value: u64, width: u32) -> i64 {
+    if width == 0 || width >= 64 {
+        return value as i64;
+    }
+    let sign_bit = 1u64 << (width - 1);
+    (value ^ sign_bit).wrapping_sub(sign_bit) as i64
+}
+
+impl Display for Binary {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(fmt, "<<")?;
+        for (i, byte) in self.data.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ",")?;
+            }
+            write!(fmt, "{}", byte)?;
+        }
+        write!(fmt, ">>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn little_endian_reverses_byte_order_not_bit_order() {
+        let mut bin = Binary::new();
+        bin.push_bits(0x0102, 16, false);
+        assert_eq!(bin, {
+            let mut expected = Binary::new();
+            expected.push_bits(0x02, 8, true);
+            expected.push_bits(0x01, 8, true);
+            expected
+        });
+    }
+
+    #[test]
+    fn little_endian_round_trips_through_read_bits() {
+        let mut bin = Binary::new();
+        bin.push_bits(0x0102, 16, false);
+        let (value, _, _) = bin.read_bits(0, 0, 16, false).unwrap();
+        assert_eq!(value, 0x0102);
+    }
+
+    #[test]
+    fn signed_read_sign_extends_negative_values() {
+        let mut bin = Binary::new();
+        // -1 as an 8-bit two's complement value is 0xFF.
+        bin.push_bits(0xFF, 8, true);
+        let (value, _, _) = bin.read_signed_bits(0, 0, 8, true).unwrap();
+        assert_eq!(value, -1);
+    }
+
+    #[test]
+    fn signed_read_leaves_positive_values_unchanged() {
+        let mut bin = Binary::new();
+        bin.push_bits(0x7F, 8, true);
+        let (value, _, _) = bin.read_signed_bits(0, 0, 8, true).unwrap();
+        assert_eq!(value, 0x7F);
+    }
+
+    #[test]
+    fn append_bits_from_copies_segments_wider_than_64_bits_without_truncating() {
+        let mut source = Binary::new();
+        for byte in 0..10u64 {
+            source.push_bits(byte + 1, 8, true);
+        }
+        assert_eq!(source.bit_len(), 80);
+
+        let mut dest = Binary::new();
+        dest.append_bits_from(&source, 0, 0, 80).unwrap();
+
+        assert_eq!(dest, source);
+    }
+
+    #[test]
+    fn append_bits_from_returns_none_when_source_is_too_short() {
+        let source = Binary::new();
+        let mut dest = Binary::new();
+        assert_eq!(dest.append_bits_from(&source, 0, 0, 8), None);
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
 #[repr(C)]
 pub enum Value {
     Register(Register),
@@ -42,7 +42,7 @@ impl Display for Value {
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
 #[repr(C)]
 pub enum Register {
     /// Global registers are available for all functions within a process, and
@@ -185,6 +185,16 @@ pub enum Test {
     },
 }
 
+/// Signedness and endianness for a bit-syntax segment, as carried by the
+/// `Bs*` instructions. Mirrors the flags BEAM packs alongside `bs_put*` and
+/// `bs_get*` ops.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct BsFlags {
+    pub signed: bool,
+    pub big_endian: bool,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[repr(C)]
 pub enum Spawn {
@@ -230,21 +240,38 @@ pub enum Instruction {
     /// Working with the Heap
     ///
 
-    /** Allocate */
+    /** Allocate `words` words on the process heap, reserving the space a
+     * stack frame is about to need.
+     *
+     * NOTE: no instruction here consumes the returned heap offset yet --
+     * `ConsList`/`MakeTuple`/`MakeLambda`/`Bs*` still build their terms
+     * inline in registers rather than boxing them on the heap. `Allocate`
+     * today only does the bookkeeping half of a real heap: tracking how much
+     * space frames use, so `Deallocate`/`mark_compact` have something to
+     * reclaim once boxed terms actually live here. */
     Allocate {
-        /** Amount of words to allocate on the heap */
         words: u8,
-        /** how many registers to preserve */
-        /** NOTE(@ostera): this is currently an artifact of how BEAM byteops
-         * work. This should be split into 2 operations: allocate + clear_many */
-        keep_registers: u8,
     },
 
-    /** Deallocate */
+    /** Deallocate the last `words` words by rewinding the bump pointer from
+     * the current end of the process heap. No stack boundary is saved at
+     * `Allocate`, so this always rewinds the end of the heap; the free-list
+     * recycling in `Heap::deallocate` is not reached from this instruction. */
     Deallocate {
         words: u8,
     },
 
+    /// Zero out local registers beyond the first `keep`, as an explicit step
+    /// independent of allocation.
+    ///
+    /// NOTE(@ostera): this used to be folded into `Allocate` as a
+    /// `keep_registers` field, an artifact of how BEAM byteops work. It is
+    /// now its own instruction so allocation and register clearing can vary
+    /// independently.
+    ClearLocals {
+        keep: u8,
+    },
+
     /// Moves the current local stack to the left by `amount`, dropping all values in its way
     ShiftLocals {
         amount: u8,
@@ -397,8 +424,190 @@ pub enum Instruction {
     /// Removes the currently selected message in the mailbox
     RemoveMessage,
 
+    /// Put the message at the mailbox's current scan position in `message`,
+    /// or jump to `on_mailbox_empty` once the scan has passed the last
+    /// message. `LoopRec` itself does not move the scan position: only
+    /// `LoopRecEnd` advances it, and `RemoveMessage` rewinds it to the
+    /// process's save mark.
+    ///
+    /// Paired with `LoopRecEnd`, this backs selective receive: a `Test` runs
+    /// against `message` after `LoopRec`, and on failure `LoopRecEnd` moves
+    /// the scan to the next entry and jumps back to retry, rather than
+    /// falling through to `on_mailbox_empty` immediately.
+    LoopRec {
+        on_mailbox_empty: Label,
+        message: Register,
+    },
+
+    /// Advance the mailbox scan to the next message after a failed `LoopRec`
+    /// match, then jump back to `retry` (the label of the `LoopRec`) to test
+    /// it.
+    LoopRecEnd {
+        retry: Label,
+    },
+
+    /// Save the current mailbox scan position as this process's save mark,
+    /// so a later `LoopRec` begins scanning there instead of from the start
+    /// of the mailbox.
+    ///
+    /// This is the `beam_receive` optimization: when a receive is known to
+    /// wait on a value created just before it (e.g. a fresh reference from
+    /// `make_ref`), no message older than the mark could possibly match, so
+    /// re-scanning them on every loop iteration would be wasted O(n) work
+    /// per message. Marking turns the whole receive into O(n) instead of
+    /// O(n^2).
+    MarkMailbox,
+
     /// Puts the identifier of the current process in a register
     PidSelf(Register),
+
+    ///////////////////////////////////////////////////////////////////////////
+    ///
+    /// Bit Syntax
+    ///
+    /// These instructions construct and match `Literal::Binary` values, as
+    /// produced by Erlang's `<<...>>` syntax. Construction appends bits to a
+    /// binary under build in `target`; matching walks a read cursor over a
+    /// source binary, advancing it by `size * unit` bits per segment.
+    ///
+
+    /// Start building a new binary in `target`. `size_hint` is the number of
+    /// bytes to pre-allocate, as a capacity hint only.
+    BsInit {
+        target: Register,
+        size_hint: u32,
+    },
+
+    /// Append `size * unit` bits of the integer in `src` to the binary being
+    /// built in `target`, according to `flags`.
+    BsPutInteger {
+        target: Register,
+        src: Value,
+        size: Value,
+        unit: u32,
+        flags: BsFlags,
+    },
+
+    /// Append `size * unit` bits taken from the binary in `src` to the binary
+    /// being built in `target`.
+    BsPutBinary {
+        target: Register,
+        src: Value,
+        size: Value,
+        unit: u32,
+    },
+
+    /// Append `size * unit` bits of a float to the binary being built in
+    /// `target`, according to `flags`. `src` must be an integer holding the
+    /// float's raw bit pattern.
+    BsPutFloat {
+        target: Register,
+        src: Value,
+        size: Value,
+        unit: u32,
+        flags: BsFlags,
+    },
+
+    /// Start a match over the binary in `bin`, placing a fresh cursor
+    /// (byte offset + bit offset) in `cursor`.
+    BsStart {
+        bin: Register,
+        cursor: Register,
+    },
+
+    /// Read `size * unit` bits off `cursor` as an integer into `target`,
+    /// advancing the cursor. Jumps to `fail` if fewer than `size * unit` bits
+    /// remain.
+    BsGetInteger {
+        cursor: Register,
+        size: Value,
+        unit: u32,
+        flags: BsFlags,
+        target: Register,
+        fail: Label,
+    },
+
+    /// Read `size * unit` bits off `cursor` as a sub-binary into `target`,
+    /// advancing the cursor. Jumps to `fail` if fewer than `size * unit` bits
+    /// remain.
+    BsGetBinary {
+        cursor: Register,
+        size: Value,
+        unit: u32,
+        target: Register,
+        fail: Label,
+    },
+
+    /// Advance `cursor` by `size * unit` bits without extracting a value.
+    /// Jumps to `fail` if fewer than `size * unit` bits remain.
+    BsSkip {
+        cursor: Register,
+        size: Value,
+        unit: u32,
+        fail: Label,
+    },
+
+    ///////////////////////////////////////////////////////////////////////////
+    ///
+    /// ETS: shared term tables
+    ///
+    /// Unlike the heap and mailbox, these tables live in a registry shared
+    /// across processes, and are looked up by name rather than by register.
+    ///
+
+    /// Create a new named table of the given kind, indexed by the tuple
+    /// element at `key_position`, and put its name on `target`.
+    EtsNew {
+        name: Atom,
+        kind: EtsTableKind,
+        key_position: u32,
+        target: Register,
+    },
+
+    /// Insert `value` into `table`, keyed by the element of the `value`
+    /// tuple at the table's `key_position` (mirroring `ets:insert/2`, which
+    /// takes a whole tuple and derives the key itself rather than being told
+    /// it separately).
+    EtsInsert {
+        table: Atom,
+        value: Value,
+    },
+
+    /// Look up `key` in `table` and place the result on `target`. Jumps to
+    /// `fail` if the key is absent.
+    EtsLookup {
+        table: Atom,
+        key: Value,
+        target: Register,
+        fail: Label,
+    },
+
+    /// Remove `key` (and its value(s)) from `table`.
+    EtsDelete {
+        table: Atom,
+        key: Value,
+    },
+
+    /// Collect every value in `table` that structurally matches `pattern`
+    /// into a list on `target`. A `Value::Nil`/`Value::Register` element
+    /// inside a tuple pattern acts as a wildcard, matching any value in that
+    /// position, mirroring an unbound variable in an `ets:match/2` pattern.
+    EtsMatch {
+        table: Atom,
+        pattern: Value,
+        target: Register,
+    },
+}
+
+/// Matches OTP `ets`'s table types: `set` allows a single value per key,
+/// `ordered_set` additionally preserves term order for range iteration, and
+/// `bag` allows multiple distinct values under the same key.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[repr(C)]
+pub enum EtsTableKind {
+    Set,
+    OrderedSet,
+    Bag,
 }
 
 impl Default for Instruction {
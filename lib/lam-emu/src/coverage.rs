@@ -0,0 +1,128 @@
+use super::bytecode::Instruction;
+use super::literal::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How many times control reached a given `(module, label)`, and whether
+/// every executed `Instruction` should be counted too, or just `Label`s.
+///
+/// Threaded through the interpreter loop as a side table: when `enabled` is
+/// `false`, `hit_label` and `hit_instruction` are no-ops, so coverage costs
+/// nothing when it's off.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    enabled: bool,
+    count_instructions: bool,
+    labels: HashMap<(Atom, Label), u64>,
+    instructions: u64,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    pub fn enable(&mut self, count_instructions: bool) {
+        self.enabled = true;
+        self.count_instructions = count_instructions;
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.labels.clear();
+        self.instructions = 0;
+    }
+
+    /// Register every label declared in `instructions` at zero hits, so
+    /// `report` can name labels that were never reached instead of only
+    /// listing the ones that were. Call this once when a module is loaded,
+    /// regardless of whether coverage is enabled -- it doesn't count as a
+    /// "hit" and costs nothing at `hit_label` time either way.
+    pub fn register_module(&mut self, module: &Atom, instructions: &[Instruction]) {
+        for instruction in instructions {
+            if let Instruction::Label(label) = instruction {
+                self.labels.entry((module.clone(), label.clone())).or_insert(0);
+            }
+        }
+    }
+
+    /// Called by the interpreter whenever control reaches `Label(label)` in
+    /// `module`.
+    pub fn hit_label(&mut self, module: &Atom, label: &Label) {
+        if !self.enabled {
+            return;
+        }
+        *self
+            .labels
+            .entry((module.clone(), label.clone()))
+            .or_insert(0) += 1;
+    }
+
+    /// Called by the interpreter for every executed `Instruction`, when
+    /// instruction-level counting is on.
+    pub fn hit_instruction(&mut self) {
+        if !self.enabled || !self.count_instructions {
+            return;
+        }
+        self.instructions += 1;
+    }
+
+    pub fn report(&self) -> CoverageReport {
+        CoverageReport {
+            labels: self
+                .labels
+                .iter()
+                .map(|((module, label), count)| LabelCoverage {
+                    module: module.clone(),
+                    label: label.clone(),
+                    hits: *count,
+                })
+                .collect(),
+            instructions_executed: self.instructions,
+        }
+    }
+}
+
+/// How many times a single label was reached; a `hits` of `0` means it was
+/// registered (e.g. as a known label in the module) but never reached.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LabelCoverage {
+    pub module: Atom,
+    pub label: Label,
+    pub hits: u64,
+}
+
+/// A serde-serializable dump of everything a `Coverage` collector observed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CoverageReport {
+    pub labels: Vec<LabelCoverage>,
+    pub instructions_executed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_module_reports_unreached_labels_at_zero_hits() {
+        let mut coverage = Coverage::new();
+        coverage.enable(false);
+        let module = Atom::from("m");
+        let instructions = vec![
+            Instruction::Label(Label(0)),
+            Instruction::Label(Label(1)),
+            Instruction::Return,
+        ];
+        coverage.register_module(&module, &instructions);
+        coverage.hit_label(&module, &Label(0));
+
+        let report = coverage.report();
+        let reached = report.labels.iter().find(|l| l.label == Label(0)).unwrap();
+        let unreached = report.labels.iter().find(|l| l.label == Label(1)).unwrap();
+        assert_eq!(reached.hits, 1);
+        assert_eq!(unreached.hits, 0);
+    }
+}
@@ -0,0 +1,202 @@
+use super::bytecode::{EtsTableKind, Value};
+use super::literal::*;
+use std::collections::{BTreeMap, HashMap};
+
+enum Storage {
+    Set(HashMap<Literal, Literal>),
+    OrderedSet(BTreeMap<Literal, Literal>),
+    Bag(HashMap<Literal, Vec<Literal>>),
+}
+
+/// A single shared term table, indexed by the element at `key_position` of
+/// the tuples inserted into it.
+pub struct Table {
+    kind: EtsTableKind,
+    key_position: u32,
+    storage: Storage,
+}
+
+impl Table {
+    fn new(kind: EtsTableKind, key_position: u32) -> Table {
+        let storage = match kind {
+            EtsTableKind::Set => Storage::Set(HashMap::new()),
+            EtsTableKind::OrderedSet => Storage::OrderedSet(BTreeMap::new()),
+            EtsTableKind::Bag => Storage::Bag(HashMap::new()),
+        };
+        Table {
+            kind,
+            key_position,
+            storage,
+        }
+    }
+
+    pub fn kind(&self) -> EtsTableKind {
+        self.kind
+    }
+
+    /// The element of `value` at this table's `key_position`, i.e. the key
+    /// `value` would be stored under. `None` if `value` isn't a tuple wide
+    /// enough to have one, or the element at that position isn't itself a
+    /// literal (e.g. an unbound register).
+    fn key_of(&self, value: &Literal) -> Option<Literal> {
+        match value {
+            Literal::Tuple(elements) => match elements.get(self.key_position as usize) {
+                Some(Value::Literal(literal)) => Some(literal.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Insert `value`, keying it by the tuple element at `key_position`.
+    /// Returns `false` without inserting if `value` has no key at that
+    /// position.
+    pub fn insert(&mut self, value: Literal) -> bool {
+        let key = match self.key_of(&value) {
+            Some(key) => key,
+            None => return false,
+        };
+        match &mut self.storage {
+            Storage::Set(map) => {
+                map.insert(key, value);
+            }
+            Storage::OrderedSet(map) => {
+                map.insert(key, value);
+            }
+            Storage::Bag(map) => {
+                let values = map.entry(key).or_insert_with(Vec::new);
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn lookup(&self, key: &Literal) -> Option<Vec<Literal>> {
+        match &self.storage {
+            Storage::Set(map) => map.get(key).cloned().map(|v| vec![v]),
+            Storage::OrderedSet(map) => map.get(key).cloned().map(|v| vec![v]),
+            Storage::Bag(map) => map.get(key).cloned(),
+        }
+    }
+
+    pub fn delete(&mut self, key: &Literal) {
+        match &mut self.storage {
+            Storage::Set(map) => {
+                map.remove(key);
+            }
+            Storage::OrderedSet(map) => {
+                map.remove(key);
+            }
+            Storage::Bag(map) => {
+                map.remove(key);
+            }
+        }
+    }
+
+    /// Every stored value that structurally matches `pattern`, the way
+    /// `ets:match/2` walks a whole table rather than just its keys.
+    pub fn match_pattern(&self, pattern: &Literal) -> Vec<Literal> {
+        let candidates: Vec<&Literal> = match &self.storage {
+            Storage::Set(map) => map.values().collect(),
+            Storage::OrderedSet(map) => map.values().collect(),
+            Storage::Bag(map) => map.values().flatten().collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|value| literal_matches(pattern, value))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Structural match between an `ets:match/2`-style `pattern` and a stored
+/// `value`: tuples match element-wise, with a `Value::Nil` or
+/// `Value::Register` element in the pattern acting as a wildcard (an
+/// unbound variable), and anything else compared by equality.
+fn literal_matches(pattern: &Literal, value: &Literal) -> bool {
+    match (pattern, value) {
+        (Literal::Tuple(pattern_elements), Literal::Tuple(value_elements)) => {
+            pattern_elements.len() == value_elements.len()
+                && pattern_elements
+                    .iter()
+                    .zip(value_elements)
+                    .all(|(p, v)| value_matches(p, v))
+        }
+        _ => pattern == value,
+    }
+}
+
+fn value_matches(pattern: &Value, value: &Value) -> bool {
+    match pattern {
+        Value::Nil | Value::Register(_) => true,
+        Value::Literal(p) => match value {
+            Value::Literal(v) => literal_matches(p, v),
+            _ => false,
+        },
+    }
+}
+
+/// A registry of named tables, living outside any single process's heap so
+/// that spawned processes can share data through it -- the `ets` analogue.
+#[derive(Default)]
+pub struct TableRegistry {
+    tables: HashMap<Atom, Table>,
+}
+
+impl TableRegistry {
+    pub fn new() -> TableRegistry {
+        TableRegistry::default()
+    }
+
+    pub fn new_table(&mut self, name: Atom, kind: EtsTableKind, key_position: u32) {
+        self.tables.insert(name, Table::new(kind, key_position));
+    }
+
+    pub fn get(&self, name: &Atom) -> Option<&Table> {
+        self.tables.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &Atom) -> Option<&mut Table> {
+        self.tables.get_mut(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuple(elements: Vec<Literal>) -> Literal {
+        Literal::Tuple(elements.into_iter().map(Value::Literal).collect())
+    }
+
+    #[test]
+    fn insert_derives_the_key_from_the_configured_tuple_position() {
+        let mut table = Table::new(EtsTableKind::Set, 0);
+        let row = tuple(vec![Literal::Atom("id-1".into()), Literal::Integer(42.into())]);
+
+        assert!(table.insert(row.clone()));
+        assert_eq!(
+            table.lookup(&Literal::Atom("id-1".into())),
+            Some(vec![row])
+        );
+    }
+
+    #[test]
+    fn match_pattern_treats_nil_elements_as_wildcards() {
+        let mut table = Table::new(EtsTableKind::Bag, 0);
+        let a = tuple(vec![Literal::Atom("k".into()), Literal::Integer(1.into())]);
+        let b = tuple(vec![Literal::Atom("k".into()), Literal::Integer(2.into())]);
+        table.insert(a.clone());
+        table.insert(b.clone());
+
+        let pattern = Literal::Tuple(vec![Value::Literal(Literal::Atom("k".into())), Value::Nil]);
+        let mut matches = table.match_pattern(&pattern);
+        matches.sort_by_key(|m| format!("{:?}", m));
+
+        let mut expected = vec![a, b];
+        expected.sort_by_key(|m| format!("{:?}", m));
+        assert_eq!(matches, expected);
+    }
+}
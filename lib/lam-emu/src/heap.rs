@@ -0,0 +1,128 @@
+use super::bytecode::Register;
+use std::collections::HashMap;
+
+/// A word offset into a process's heap, as returned by `Heap::allocate`. No
+/// instruction consumes it (see `Instruction::Allocate`'s doc comment).
+pub type HeapOffset = usize;
+
+#[derive(Debug, Clone, Copy)]
+struct FreeSpan {
+    offset: HeapOffset,
+    words: usize,
+}
+
+/// A per-process bump allocator, in the spirit of `talc`'s span + free-list
+/// design: allocation is a pointer bump, deallocation rewinds the pointer
+/// when possible and otherwise recycles the span onto a free list, and a
+/// mark/compact pass reclaims whatever the free list alone couldn't.
+#[derive(Debug, Clone, Default)]
+pub struct Heap {
+    words: Vec<u64>,
+    free_list: Vec<FreeSpan>,
+}
+
+impl Heap {
+    pub fn new() -> Heap {
+        Heap::default()
+    }
+
+    pub fn len_words(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Bump-allocate `words` words, reusing a free-list span if one is large
+    /// enough, and growing the heap otherwise.
+    pub fn allocate(&mut self, words: u8) -> HeapOffset {
+        let words = words as usize;
+        if let Some(index) = self
+            .free_list
+            .iter()
+            .position(|span| span.words >= words)
+        {
+            let span = self.free_list.remove(index);
+            if span.words > words {
+                self.free_list.push(FreeSpan {
+                    offset: span.offset + words,
+                    words: span.words - words,
+                });
+            }
+            return span.offset;
+        }
+        let offset = self.words.len();
+        self.words.resize(offset + words, 0);
+        offset
+    }
+
+    /// Reclaim the last `words` words. If they sit at the very end of the
+    /// heap, the bump pointer is simply rewound; otherwise the span is
+    /// recycled onto the free list for a future `allocate` to reuse.
+    pub fn deallocate(&mut self, boundary: HeapOffset, words: u8) {
+        let words = words as usize;
+        let offset = boundary.saturating_sub(words);
+        if boundary == self.words.len() {
+            self.words.truncate(offset);
+        } else {
+            self.free_list.push(FreeSpan { offset, words });
+        }
+    }
+
+    /// Walk live registers as roots, dropping everything else and
+    /// compacting the heap down to just what's reachable. `roots` gives, for
+    /// each live register, the heap offset and size (in words) of the value
+    /// it points to.
+    ///
+    /// Compaction moves every surviving span to a new offset, so it returns
+    /// each root register's new offset: the caller MUST rewrite its register->offset
+    /// bindings from this map before touching the heap again, or every
+    /// register still holding a pre-compaction offset is dangling.
+    ///
+    /// Not yet called from the interpreter: no instruction boxes a term on
+    /// the heap yet (see `Instruction::Allocate`'s doc comment), so there is
+    /// nothing for a GC pass to collect against. This is the primitive a
+    /// future GC trigger will drive once boxed terms exist.
+    pub fn mark_compact(&mut self, roots: &[(Register, HeapOffset, usize)]) -> HashMap<Register, HeapOffset> {
+        let mut spans: Vec<(HeapOffset, usize)> =
+            roots.iter().map(|(_, offset, words)| (*offset, *words)).collect();
+        spans.sort_by_key(|(offset, _)| *offset);
+        spans.dedup();
+
+        let mut compacted = Vec::with_capacity(self.words.len());
+        let mut new_offset_of = HashMap::new();
+        for (offset, words) in &spans {
+            let start = *offset;
+            let end = (start + words).min(self.words.len());
+            new_offset_of.insert(*offset, compacted.len());
+            compacted.extend_from_slice(&self.words[start..end]);
+        }
+
+        self.words = compacted;
+        self.free_list.clear();
+
+        roots
+            .iter()
+            .map(|(register, offset, _)| (register.clone(), new_offset_of[offset]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_compact_remaps_surviving_registers_to_their_new_offsets() {
+        let mut heap = Heap::new();
+        let dead = heap.allocate(4);
+        let live = heap.allocate(2);
+        heap.words[dead] = 0xDEAD;
+        heap.words[live] = 0xA1A1;
+        heap.words[live + 1] = 0xB2B2;
+
+        let remap = heap.mark_compact(&[(Register::Global(0), live, 2)]);
+
+        let new_offset = remap[&Register::Global(0)];
+        assert_eq!(heap.words[new_offset], 0xA1A1);
+        assert_eq!(heap.words[new_offset + 1], 0xB2B2);
+        assert_eq!(heap.len_words(), 2);
+    }
+}
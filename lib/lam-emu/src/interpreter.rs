@@ -0,0 +1,1160 @@
+use super::binary::Binary;
+use super::bytecode::{FnCall, Instruction, Register, Spawn as SpawnSpec, Value};
+use super::coverage::Coverage;
+use super::ets::TableRegistry;
+use super::literal::{Atom, Label, List, Literal, Pid, MFA};
+use super::process::Process;
+use super::profiler::{Clock, Profiler};
+use super::trace::{TraceEvent, Tracer};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+/// A side effect `step` could not resolve on its own because it needs
+/// coordination across processes -- delivering a message to another
+/// process's mailbox, or registering a freshly spawned one -- or because it
+/// is a control-flow jump the caller's program counter must act on.
+#[derive(Debug, Clone)]
+pub enum Effect {
+    None,
+    Jump(Label),
+    Send { to: Literal, message: Literal },
+    Spawned { child: Pid },
+}
+
+fn resolve(process: &Process, value: &Value) -> Literal {
+    match value {
+        Value::Literal(literal) => literal.clone(),
+        Value::Register(register) => process.registers.get(register).into(),
+        Value::Nil => panic!("Can not resolve Nil to a Literal"),
+    }
+}
+
+fn pid_of(literal: &Literal) -> Pid {
+    match literal {
+        Literal::Pid(pid) => *pid,
+        other => panic!("Can not turn {:?} into a Pid", other),
+    }
+}
+
+fn mfa_of(call: &FnCall) -> Option<MFA> {
+    match call {
+        FnCall::Qualified {
+            module,
+            function,
+            arity,
+        } => Some(MFA {
+            module: module.clone(),
+            function: function.clone(),
+            arity: *arity,
+        }),
+        FnCall::BuiltIn {
+            module,
+            function,
+            arity,
+            ..
+        } => Some(MFA {
+            module: module.clone(),
+            function: function.clone(),
+            arity: *arity,
+        }),
+        // A local call has no function name in the bytecode, only the label
+        // it jumps to -- synthesize an atom from that label so intra-module
+        // calls (the common case) still fire a trace `Call` event and a
+        // profiler frame instead of being silently dropped.
+        FnCall::Local { module, label, arity } => Some(MFA {
+            module: module.clone(),
+            function: Atom::from(label.to_string()),
+            arity: *arity,
+        }),
+        FnCall::ApplyLambda { .. } => None,
+    }
+}
+
+fn resolve_u64(process: &Process, value: &Value) -> u64 {
+    match resolve(process, value) {
+        Literal::Integer(i) => i.to_u64().unwrap_or(0),
+        other => panic!("expected an integer, got {:?}", other),
+    }
+}
+
+/// The in-construction binary currently held in `target`, or a fresh one if
+/// `target` doesn't hold a binary yet (as is the case right after `BsInit`).
+fn binary_in(process: &Process, target: &Register) -> Binary {
+    match process.registers.get(target) {
+        Value::Literal(Literal::Binary(binary)) => binary,
+        _ => Binary::new(),
+    }
+}
+
+/// A `Bs*` match cursor is carried in a register as a 3-tuple of the source
+/// binary and its current `(byte_offset, bit_offset)`, since there is no
+/// dedicated cursor term -- `BsStart` creates one, and every `BsGet*`/`BsSkip`
+/// reads and rewrites it as the read head advances.
+fn encode_cursor(binary: Binary, byte_offset: usize, bit_offset: u8) -> Literal {
+    Literal::Tuple(vec![
+        Value::Literal(Literal::Binary(binary)),
+        Value::Literal(Literal::Integer(BigInt::from(byte_offset as u64))),
+        Value::Literal(Literal::Integer(BigInt::from(bit_offset))),
+    ])
+}
+
+fn decode_cursor(literal: Literal) -> (Binary, usize, u8) {
+    match literal {
+        Literal::Tuple(elements) if elements.len() == 3 => {
+            let mut elements = elements.into_iter();
+            let binary = match elements.next() {
+                Some(Value::Literal(Literal::Binary(binary))) => binary,
+                other => panic!("bs cursor is missing its binary, got {:?}", other),
+            };
+            let byte_offset = match elements.next() {
+                Some(Value::Literal(Literal::Integer(i))) => i.to_u64().unwrap_or(0) as usize,
+                other => panic!("bs cursor is missing its byte offset, got {:?}", other),
+            };
+            let bit_offset = match elements.next() {
+                Some(Value::Literal(Literal::Integer(i))) => i.to_u64().unwrap_or(0) as u8,
+                other => panic!("bs cursor is missing its bit offset, got {:?}", other),
+            };
+            (binary, byte_offset, bit_offset)
+        }
+        other => panic!("{:?} is not a bs cursor", other),
+    }
+}
+
+/// Drives a single process through its instruction stream, firing `Tracer`
+/// callbacks at the points the tracing request calls for: `Call`/`TailCall`,
+/// `Return`, `Send`, `PeekMessage`/`RemoveMessage`, and `Spawn`. `Call` and
+/// `TailCall` also feed the `Profiler`, which charges elapsed time to
+/// whichever MFA is on top of its stack at `Return`.
+pub struct Interpreter<T: Tracer, C: Clock> {
+    pub tracer: T,
+    pub coverage: Coverage,
+    pub ets: TableRegistry,
+    pub profiler: Profiler<C>,
+    next_pid: u64,
+}
+
+impl<T: Tracer, C: Clock> Interpreter<T, C> {
+    pub fn new(tracer: T, clock: C) -> Interpreter<T, C> {
+        Interpreter {
+            tracer,
+            coverage: Coverage::new(),
+            ets: TableRegistry::new(),
+            profiler: Profiler::new(clock),
+            next_pid: 1,
+        }
+    }
+
+    /// Execute a single instruction against `process`. Anything that needs
+    /// coordination beyond this one process is handed back as an `Effect`
+    /// for the caller (a scheduler) to carry out.
+    pub fn step(&mut self, module: &Atom, process: &mut Process, instruction: &Instruction) -> Effect {
+        self.coverage.hit_instruction();
+
+        match instruction {
+            Instruction::Label(label) => {
+                self.coverage.hit_label(module, label);
+                Effect::None
+            }
+
+            Instruction::Call(call, _) => {
+                if let Some(mfa) = mfa_of(call) {
+                    let args = match call {
+                        FnCall::BuiltIn { arguments, .. } => {
+                            arguments.iter().map(|v| resolve(process, v)).collect()
+                        }
+                        _ => vec![],
+                    };
+                    // A BIF resolves inline and never reaches `Return`, so
+                    // leaving a profiler/call-stack frame for it here would
+                    // keep that frame forever, misattributing elapsed time
+                    // (and `Return`'s MFA) to whatever real function returns
+                    // next. Its profiler frame is entered and exited on the
+                    // spot instead: the call is counted, with no time spent.
+                    if matches!(call, FnCall::BuiltIn { .. }) {
+                        self.profiler.enter(mfa.clone());
+                        self.profiler.exit();
+                    } else {
+                        self.profiler.enter(mfa.clone());
+                        process.call_stack.push(mfa.clone());
+                    }
+                    self.tracer.emit(TraceEvent::Call {
+                        pid: process.pid,
+                        mfa,
+                        args,
+                    });
+                }
+                Effect::None
+            }
+
+            Instruction::TailCall(call, _) => {
+                if let Some(mfa) = mfa_of(call) {
+                    let args = match call {
+                        FnCall::BuiltIn { arguments, .. } => {
+                            arguments.iter().map(|v| resolve(process, v)).collect()
+                        }
+                        _ => vec![],
+                    };
+                    // Same reasoning as `Call` above: a tail call into a BIF
+                    // must not replace the current frame either, since the
+                    // BIF itself will never trigger a `Return`; it is only
+                    // counted.
+                    if matches!(call, FnCall::BuiltIn { .. }) {
+                        self.profiler.enter(mfa.clone());
+                        self.profiler.exit();
+                    } else {
+                        self.profiler.tail_enter(mfa.clone());
+                        process.call_stack.replace(mfa.clone());
+                    }
+                    self.tracer.emit(TraceEvent::Call {
+                        pid: process.pid,
+                        mfa,
+                        args,
+                    });
+                }
+                Effect::None
+            }
+
+            Instruction::Return => {
+                self.profiler.exit();
+                // Return carries no MFA of its own -- attribute it to
+                // whatever Call/TailCall pushed the frame we're unwinding,
+                // falling back to the placeholder only if the stack is
+                // unexpectedly empty (e.g. a Return with no matching Call).
+                let mfa = process.call_stack.pop().unwrap_or_else(|| MFA {
+                    module: module.clone(),
+                    function: Atom::from("?"),
+                    arity: 0,
+                });
+                // An empty `Global(0)` is reported as `[]` (BEAM's nil)
+                // rather than resolved, which would panic.
+                let value = match process.registers.get(&Register::Global(0)) {
+                    Value::Nil => Literal::List(List::Nil),
+                    value => resolve(process, &value),
+                };
+                self.tracer.emit(TraceEvent::Return {
+                    pid: process.pid,
+                    mfa,
+                    value,
+                });
+                Effect::None
+            }
+
+            Instruction::Send { message, process: target } => {
+                let message = resolve(process, message);
+                let to = resolve(process, target);
+                self.tracer.emit(TraceEvent::Send {
+                    from: process.pid,
+                    to: pid_of(&to),
+                    message: message.clone(),
+                });
+                Effect::Send { to, message }
+            }
+
+            Instruction::PeekMessage {
+                on_mailbox_empty,
+                message,
+            } => match process.mailbox.current().cloned() {
+                Some(current) => {
+                    process.registers.set(message, Value::Literal(current.clone()));
+                    self.tracer.emit(TraceEvent::Receive {
+                        pid: process.pid,
+                        message: current,
+                    });
+                    Effect::None
+                }
+                None => Effect::Jump(on_mailbox_empty.clone()),
+            },
+
+            Instruction::RemoveMessage => {
+                process.mailbox.remove_current();
+                Effect::None
+            }
+
+            Instruction::Spawn(spec) => {
+                let child = Pid(self.next_pid);
+                self.next_pid += 1;
+                let mfa = match spec {
+                    SpawnSpec::MFA {
+                        module,
+                        function,
+                        arity,
+                        ..
+                    } => MFA {
+                        module: module.clone(),
+                        function: function.clone(),
+                        arity: *arity,
+                    },
+                    SpawnSpec::Lambda { .. } => MFA {
+                        module: module.clone(),
+                        function: Atom::from("lambda"),
+                        arity: 0,
+                    },
+                };
+                self.tracer.emit(TraceEvent::Spawn {
+                    parent: process.pid,
+                    child,
+                    mfa,
+                });
+                process
+                    .registers
+                    .set(&Register::Global(0), Value::Literal(Literal::Pid(child)));
+                Effect::Spawned { child }
+            }
+
+            Instruction::EtsNew {
+                name,
+                kind,
+                key_position,
+                target,
+            } => {
+                self.ets.new_table(name.clone(), *kind, *key_position);
+                process
+                    .registers
+                    .set(target, Value::Literal(Literal::Atom(name.as_str().to_string())));
+                Effect::None
+            }
+
+            Instruction::EtsInsert { table, value } => {
+                let value = resolve(process, value);
+                if let Some(table) = self.ets.get_mut(table) {
+                    table.insert(value);
+                }
+                Effect::None
+            }
+
+            Instruction::EtsLookup {
+                table,
+                key,
+                target,
+                fail,
+            } => {
+                let key = resolve(process, key);
+                match self.ets.get(table).and_then(|t| t.lookup(&key)) {
+                    Some(mut values) if !values.is_empty() => {
+                        process.registers.set(target, Value::Literal(values.remove(0)));
+                        Effect::None
+                    }
+                    _ => Effect::Jump(fail.clone()),
+                }
+            }
+
+            Instruction::EtsDelete { table, key } => {
+                let key = resolve(process, key);
+                if let Some(table) = self.ets.get_mut(table) {
+                    table.delete(&key);
+                }
+                Effect::None
+            }
+
+            Instruction::EtsMatch {
+                table,
+                pattern,
+                target,
+            } => {
+                let pattern = resolve(process, pattern);
+                let matches = self
+                    .ets
+                    .get(table)
+                    .map(|t| t.match_pattern(&pattern))
+                    .unwrap_or_default();
+                let list = matches.into_iter().rev().fold(List::Nil, |tail, head| {
+                    List::Cons(Box::new(Value::Literal(head)), Box::new(tail))
+                });
+                process.registers.set(target, Value::Literal(Literal::List(list)));
+                Effect::None
+            }
+
+            Instruction::Allocate { words } => {
+                // The returned offset is discarded: nothing boxes a term on
+                // the heap yet, so there's nowhere to stash it. See
+                // `Instruction::Allocate`'s doc comment.
+                process.heap.allocate(*words);
+                Effect::None
+            }
+
+            Instruction::Deallocate { words } => {
+                let boundary = process.heap.len_words();
+                process.heap.deallocate(boundary, *words);
+                Effect::None
+            }
+
+            Instruction::ClearLocals { keep } => {
+                process.registers.clear_locals(*keep);
+                Effect::None
+            }
+
+            Instruction::LoopRec {
+                on_mailbox_empty,
+                message,
+            } => {
+                if process.mailbox.is_exhausted() {
+                    return Effect::Jump(on_mailbox_empty.clone());
+                }
+                let current = process.mailbox.current().cloned().unwrap();
+                process.registers.set(message, Value::Literal(current));
+                Effect::None
+            }
+
+            Instruction::LoopRecEnd { retry } => {
+                process.mailbox.advance();
+                Effect::Jump(retry.clone())
+            }
+
+            Instruction::MarkMailbox => {
+                process.mailbox.mark();
+                Effect::None
+            }
+
+            Instruction::BsInit { target, size_hint } => {
+                process
+                    .registers
+                    .set(target, Value::Literal(Literal::Binary(Binary::with_capacity(*size_hint as usize))));
+                Effect::None
+            }
+
+            Instruction::BsPutInteger {
+                target,
+                src,
+                size,
+                unit,
+                flags,
+            } => {
+                let mut binary = binary_in(process, target);
+                // Negative integers are written in two's complement.
+                let value = match resolve(process, src) {
+                    Literal::Integer(i) => i.to_i64().map(|v| v as u64).or_else(|| i.to_u64()).unwrap_or(0),
+                    other => panic!("expected an integer, got {:?}", other),
+                };
+                let width = resolve_u64(process, size) as u32 * unit;
+                binary.push_bits(value, width, flags.big_endian);
+                process.registers.set(target, Value::Literal(Literal::Binary(binary)));
+                Effect::None
+            }
+
+            Instruction::BsPutBinary { target, src, size, unit } => {
+                let mut binary = binary_in(process, target);
+                let source = match resolve(process, src) {
+                    Literal::Binary(source) => source,
+                    other => panic!("BsPutBinary src is not a binary: {:?}", other),
+                };
+                let width = resolve_u64(process, size) as u32 * unit;
+                // Copied bit-by-bit via `append_bits_from`, not through
+                // `read_bits`/`push_bits`: those round-trip through a `u64`
+                // and silently truncate any segment over 64 bits wide.
+                binary.append_bits_from(&source, 0, 0, width);
+                process.registers.set(target, Value::Literal(Literal::Binary(binary)));
+                Effect::None
+            }
+
+            Instruction::BsPutFloat {
+                target,
+                src,
+                size,
+                unit,
+                flags,
+            } => {
+                // There is no dedicated float literal yet, so `src` is taken
+                // as the float's raw bit pattern, the same way BsPutInteger
+                // treats its source.
+                let mut binary = binary_in(process, target);
+                let value = resolve_u64(process, src);
+                let width = resolve_u64(process, size) as u32 * unit;
+                binary.push_bits(value, width, flags.big_endian);
+                process.registers.set(target, Value::Literal(Literal::Binary(binary)));
+                Effect::None
+            }
+
+            Instruction::BsStart { bin, cursor } => {
+                let binary = match resolve(process, &Value::Register(bin.clone())) {
+                    Literal::Binary(binary) => binary,
+                    other => panic!("BsStart bin is not a binary: {:?}", other),
+                };
+                process
+                    .registers
+                    .set(cursor, Value::Literal(encode_cursor(binary, 0, 0)));
+                Effect::None
+            }
+
+            Instruction::BsGetInteger {
+                cursor,
+                size,
+                unit,
+                flags,
+                target,
+                fail,
+            } => {
+                let (binary, byte_offset, bit_offset) =
+                    decode_cursor(resolve(process, &Value::Register(cursor.clone())));
+                let width = resolve_u64(process, size) as u32 * unit;
+                let read = if flags.signed {
+                    binary
+                        .read_signed_bits(byte_offset, bit_offset, width, flags.big_endian)
+                        .map(|(value, byte, bit)| (Literal::Integer(BigInt::from(value)), byte, bit))
+                } else {
+                    binary
+                        .read_bits(byte_offset, bit_offset, width, flags.big_endian)
+                        .map(|(value, byte, bit)| (Literal::Integer(BigInt::from(value)), byte, bit))
+                };
+                match read {
+                    Some((value, new_byte, new_bit)) => {
+                        process.registers.set(target, Value::Literal(value));
+                        process
+                            .registers
+                            .set(cursor, Value::Literal(encode_cursor(binary, new_byte, new_bit)));
+                        Effect::None
+                    }
+                    None => Effect::Jump(fail.clone()),
+                }
+            }
+
+            Instruction::BsGetBinary {
+                cursor,
+                size,
+                unit,
+                target,
+                fail,
+            } => {
+                let (binary, byte_offset, bit_offset) =
+                    decode_cursor(resolve(process, &Value::Register(cursor.clone())));
+                let width = resolve_u64(process, size) as u32 * unit;
+                // Extracted bit-by-bit via `append_bits_from`, not through
+                // `read_bits`/`push_bits`: those round-trip through a `u64`
+                // and silently truncate any segment over 64 bits wide.
+                let mut extracted = Binary::new();
+                match extracted.append_bits_from(&binary, byte_offset, bit_offset, width) {
+                    Some(()) => {
+                        let new_bits = byte_offset * 8 + bit_offset as usize + width as usize;
+                        let new_byte = new_bits / 8;
+                        let new_bit = (new_bits % 8) as u8;
+                        process.registers.set(target, Value::Literal(Literal::Binary(extracted)));
+                        process
+                            .registers
+                            .set(cursor, Value::Literal(encode_cursor(binary, new_byte, new_bit)));
+                        Effect::None
+                    }
+                    None => Effect::Jump(fail.clone()),
+                }
+            }
+
+            Instruction::BsSkip { cursor, size, unit, fail } => {
+                let (binary, byte_offset, bit_offset) =
+                    decode_cursor(resolve(process, &Value::Register(cursor.clone())));
+                let width = resolve_u64(process, size) as u32 * unit;
+                // Pure cursor arithmetic, not `read_bits`, so a skip of any
+                // width works instead of hitting its 64-bit cap.
+                let new_bits = byte_offset * 8 + bit_offset as usize + width as usize;
+                if new_bits > binary.bit_len() {
+                    return Effect::Jump(fail.clone());
+                }
+                let (new_byte, new_bit) = (new_bits / 8, (new_bits % 8) as u8);
+                process
+                    .registers
+                    .set(cursor, Value::Literal(encode_cursor(binary, new_byte, new_bit)));
+                Effect::None
+            }
+
+            _ => Effect::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::FnKind;
+    use crate::trace::{RecordingTracer, TraceFlags};
+
+    #[test]
+    fn call_and_return_are_captured_as_trace_events() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::all(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let call = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("double"),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(call, FnKind::User));
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        let events = interpreter.tracer.events;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], TraceEvent::Call { .. }));
+        assert!(matches!(events[1], TraceEvent::Return { .. }));
+    }
+
+    #[test]
+    fn local_calls_fire_a_trace_call_event_instead_of_being_dropped() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::all(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let call = FnCall::Local {
+            module: Atom::from("math"),
+            label: Label(3),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(call, FnKind::User));
+
+        assert!(matches!(interpreter.tracer.events[0], TraceEvent::Call { .. }));
+    }
+
+    #[test]
+    fn nested_returns_are_attributed_to_the_function_actually_returning() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::all(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let outer = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("outer"),
+            arity: 0,
+        };
+        let inner = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("inner"),
+            arity: 0,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(outer, FnKind::User));
+        interpreter.step(&module, &mut process, &Instruction::Call(inner, FnKind::User));
+        interpreter.step(&module, &mut process, &Instruction::Return);
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        let returns: Vec<&Atom> = interpreter
+            .tracer
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                TraceEvent::Return { mfa, .. } => Some(&mfa.function),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(returns, vec![&Atom::from("inner"), &Atom::from("outer")]);
+    }
+
+    #[test]
+    fn tracing_is_skipped_for_event_classes_not_enabled() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let call = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("double"),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(call, FnKind::User));
+
+        assert!(interpreter.tracer.events.is_empty());
+    }
+
+    #[test]
+    fn stepping_a_label_records_coverage_when_enabled() {
+        use crate::literal::Label;
+
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        interpreter.coverage.enable(true);
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        interpreter.step(&module, &mut process, &Instruction::Label(Label(0)));
+        interpreter.step(&module, &mut process, &Instruction::Label(Label(0)));
+
+        let report = interpreter.coverage.report();
+        let label0 = report.labels.iter().find(|l| l.label == Label(0)).unwrap();
+        assert_eq!(label0.hits, 2);
+        assert_eq!(report.instructions_executed, 2);
+    }
+
+    #[test]
+    fn ets_insert_and_lookup_round_trip_through_the_interpreter() {
+        use crate::bytecode::EtsTableKind;
+
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::EtsNew {
+                name: Atom::from("people"),
+                kind: EtsTableKind::Set,
+                key_position: 0,
+                target: Register::Global(0),
+            },
+        );
+
+        let row = Literal::Tuple(vec![
+            Value::Literal(Literal::Atom("alice".into())),
+            Value::Literal(Literal::Integer(30.into())),
+        ]);
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::EtsInsert {
+                table: Atom::from("people"),
+                value: Value::Literal(row.clone()),
+            },
+        );
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::EtsLookup {
+                table: Atom::from("people"),
+                key: Value::Literal(Literal::Atom("alice".into())),
+                target: Register::Global(1),
+                fail: Label(99),
+            },
+        );
+
+        assert!(matches!(effect, Effect::None));
+        assert_eq!(process.registers.get(&Register::Global(1)), Value::Literal(row));
+    }
+
+    #[test]
+    fn ets_lookup_jumps_to_fail_when_the_key_is_absent() {
+        use crate::bytecode::EtsTableKind;
+
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::EtsNew {
+                name: Atom::from("people"),
+                kind: EtsTableKind::Set,
+                key_position: 0,
+                target: Register::Global(0),
+            },
+        );
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::EtsLookup {
+                table: Atom::from("people"),
+                key: Value::Literal(Literal::Atom("missing".into())),
+                target: Register::Global(1),
+                fail: Label(99),
+            },
+        );
+
+        assert!(matches!(effect, Effect::Jump(Label(99))));
+    }
+
+    #[derive(Clone)]
+    struct SharedFakeClock {
+        now: std::rc::Rc<std::cell::Cell<f64>>,
+    }
+
+    impl SharedFakeClock {
+        fn new() -> SharedFakeClock {
+            SharedFakeClock {
+                now: std::rc::Rc::new(std::cell::Cell::new(0.0)),
+            }
+        }
+
+        fn set(&self, millis: f64) {
+            self.now.set(millis);
+        }
+    }
+
+    impl crate::profiler::Clock for SharedFakeClock {
+        fn now_millis(&self) -> f64 {
+            self.now.get()
+        }
+    }
+
+    #[test]
+    fn call_and_return_charge_elapsed_time_to_the_profiler() {
+        let clock = SharedFakeClock::new();
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            clock.clone(),
+        );
+        interpreter.profiler.start();
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let call = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("double"),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(call, FnKind::User));
+        clock.set(10.0);
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        let report = interpreter.profiler.report();
+        let entry = report
+            .entries
+            .iter()
+            .find(|e| e.mfa.function.as_str() == "double")
+            .unwrap();
+        assert_eq!(entry.calls, 1);
+        assert_eq!(entry.total_millis, 10.0);
+    }
+
+    #[test]
+    fn tail_call_replaces_the_current_profiler_frame() {
+        let clock = SharedFakeClock::new();
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            clock.clone(),
+        );
+        interpreter.profiler.start();
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let call = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("loop"),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(call.clone(), FnKind::User));
+        clock.set(5.0);
+        interpreter.step(&module, &mut process, &Instruction::TailCall(call, FnKind::User));
+        clock.set(10.0);
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        let report = interpreter.profiler.report();
+        let entry = report
+            .entries
+            .iter()
+            .find(|e| e.mfa.function.as_str() == "loop")
+            .unwrap();
+        assert_eq!(entry.calls, 2);
+        assert_eq!(entry.total_millis, 10.0);
+    }
+
+    #[test]
+    fn allocate_deallocate_and_clear_locals_drive_the_process_heap_and_registers() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+        process
+            .registers
+            .set(&Register::Local(0), Value::Literal(Literal::Integer(1.into())));
+
+        interpreter.step(&module, &mut process, &Instruction::Allocate { words: 4 });
+        assert_eq!(process.heap.len_words(), 4);
+
+        interpreter.step(&module, &mut process, &Instruction::ClearLocals { keep: 0 });
+        assert_eq!(process.registers.get(&Register::Local(0)), Value::Nil);
+
+        interpreter.step(&module, &mut process, &Instruction::Deallocate { words: 4 });
+        assert_eq!(process.heap.len_words(), 0);
+    }
+
+    #[test]
+    fn loop_rec_end_skips_each_message_until_loop_rec_jumps_on_empty() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+        process.mailbox.push(Literal::Atom("first".into()));
+        process.mailbox.push(Literal::Atom("second".into()));
+
+        let retry = Label(0);
+        let empty = Label(1);
+        let message = Register::Local(0);
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRec {
+                on_mailbox_empty: empty.clone(),
+                message: message.clone(),
+            },
+        );
+        assert!(matches!(effect, Effect::None));
+        assert_eq!(
+            process.registers.get(&message),
+            Value::Literal(Literal::Atom("first".into()))
+        );
+
+        let effect = interpreter.step(&module, &mut process, &Instruction::LoopRecEnd { retry: retry.clone() });
+        assert!(matches!(effect, Effect::Jump(ref label) if *label == retry));
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRec {
+                on_mailbox_empty: empty.clone(),
+                message: message.clone(),
+            },
+        );
+        assert!(matches!(effect, Effect::None));
+        assert_eq!(
+            process.registers.get(&message),
+            Value::Literal(Literal::Atom("second".into()))
+        );
+
+        let effect = interpreter.step(&module, &mut process, &Instruction::LoopRecEnd { retry });
+        assert!(matches!(effect, Effect::Jump(_)));
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRec {
+                on_mailbox_empty: empty.clone(),
+                message,
+            },
+        );
+        assert!(matches!(effect, Effect::Jump(ref label) if *label == empty));
+    }
+
+    #[test]
+    fn builtin_calls_do_not_leave_a_dangling_profiler_frame() {
+        let clock = SharedFakeClock::new();
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            clock.clone(),
+        );
+        interpreter.profiler.start();
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        // A BIF resolves inline and is never followed by `Return`. If `Call`
+        // pushed a profiler frame for it anyway, that frame would sit on the
+        // stack forever and get popped by some later, unrelated `Return`.
+        let builtin = FnCall::BuiltIn {
+            module: Atom::from("erlang"),
+            function: Atom::from("length"),
+            arity: 1,
+            arguments: vec![],
+            destination: Register::Global(0),
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(builtin, FnKind::User));
+
+        let real_call = FnCall::Qualified {
+            module: Atom::from("math"),
+            function: Atom::from("double"),
+            arity: 1,
+        };
+        interpreter.step(&module, &mut process, &Instruction::Call(real_call, FnKind::User));
+        clock.set(10.0);
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        // This `Return` has no matching `Call` of its own: it only exists to
+        // show the stack is already empty by this point. If the BuiltIn call
+        // above had left a frame behind, this would wrongly pop it and
+        // attribute 30ms to `erlang:length/1`.
+        clock.set(30.0);
+        interpreter.step(&module, &mut process, &Instruction::Return);
+
+        let report = interpreter.profiler.report();
+        let builtin_entry = report
+            .entries
+            .iter()
+            .find(|e| e.mfa.function.as_str() == "length")
+            .unwrap();
+        assert_eq!(builtin_entry.total_millis, 0.0);
+    }
+
+    #[test]
+    fn bit_syntax_construct_and_match_round_trip_through_the_interpreter() {
+        use crate::bytecode::BsFlags;
+
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let built = Register::Local(0);
+        interpreter.step(&module, &mut process, &Instruction::BsInit { target: built.clone(), size_hint: 1 });
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::BsPutInteger {
+                target: built.clone(),
+                src: Value::Literal(Literal::Integer(200.into())),
+                size: Value::Literal(Literal::Integer(8.into())),
+                unit: 1,
+                flags: BsFlags::default(),
+            },
+        );
+
+        let cursor = Register::Local(2);
+        interpreter.step(&module, &mut process, &Instruction::BsStart { bin: built, cursor: cursor.clone() });
+
+        let target = Register::Local(3);
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::BsGetInteger {
+                cursor,
+                size: Value::Literal(Literal::Integer(8.into())),
+                unit: 1,
+                flags: BsFlags::default(),
+                target: target.clone(),
+                fail: Label(99),
+            },
+        );
+
+        assert!(matches!(effect, Effect::None));
+        assert_eq!(
+            process.registers.get(&target),
+            Value::Literal(Literal::Integer(200.into()))
+        );
+    }
+
+    #[test]
+    fn bs_put_binary_copies_segments_wider_than_64_bits_without_truncating() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+
+        let mut source = Binary::new();
+        for byte in 0..10u64 {
+            source.push_bits(byte + 1, 8, true);
+        }
+        let src = Register::Local(0);
+        process.registers.set(&src, Value::Literal(Literal::Binary(source.clone())));
+
+        let target = Register::Local(1);
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::BsPutBinary {
+                target: target.clone(),
+                src: Value::Register(src),
+                size: Value::Literal(Literal::Integer(10.into())),
+                unit: 8,
+            },
+        );
+
+        assert_eq!(
+            process.registers.get(&target),
+            Value::Literal(Literal::Binary(source))
+        );
+    }
+
+    #[test]
+    fn mark_mailbox_makes_a_later_remove_resume_scanning_from_the_mark_not_the_start() {
+        let mut interpreter = Interpreter::new(
+            RecordingTracer {
+                flags: TraceFlags::none(),
+                events: vec![],
+            },
+            crate::profiler::InstantClock::new(),
+        );
+        let module = Atom::from("math");
+        let mut process = Process::new(Pid(1));
+        process.mailbox.push(Literal::Atom("stale".into()));
+
+        // Skip past "stale" once, then mark -- a later reset_to_mark should
+        // never walk back over it.
+        interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRecEnd { retry: Label(0) },
+        );
+        interpreter.step(&module, &mut process, &Instruction::MarkMailbox);
+        process.mailbox.push(Literal::Atom("fresh".into()));
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRec {
+                on_mailbox_empty: Label(1),
+                message: Register::Local(0),
+            },
+        );
+        assert!(matches!(effect, Effect::None));
+        assert_eq!(
+            process.registers.get(&Register::Local(0)),
+            Value::Literal(Literal::Atom("fresh".into()))
+        );
+
+        interpreter.step(&module, &mut process, &Instruction::RemoveMessage);
+
+        let effect = interpreter.step(
+            &module,
+            &mut process,
+            &Instruction::LoopRec {
+                on_mailbox_empty: Label(1),
+                message: Register::Local(0),
+            },
+        );
+        assert!(matches!(effect, Effect::Jump(Label(1))));
+    }
+}
@@ -0,0 +1,18 @@
+pub mod binary;
+pub mod bytecode;
+pub mod coverage;
+pub mod ets;
+pub mod heap;
+pub mod interpreter;
+pub mod literal;
+pub mod process;
+pub mod profiler;
+mod runtime;
+pub mod trace;
+
+pub use binary::Binary;
+pub use bytecode::{Register, Value};
+pub use literal::{Atom, Label, List, Literal, Pid, MFA};
+pub use profiler::Clock;
+pub use runtime::Runtime;
+pub use trace::{TraceEvent, TraceFlags, Tracer};
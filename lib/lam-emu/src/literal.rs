@@ -0,0 +1,201 @@
+use super::bytecode::Value;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use super::binary::Binary;
+
+pub type Arity = u32;
+
+/// An Erlang atom, stored as a plain `String`. `Literal::Atom` holds its own
+/// `String` rather than an `Atom`, so atom terms do not go through this type.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
+#[repr(C)]
+pub struct Atom(String);
+
+impl Atom {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(s: &str) -> Atom {
+        Atom(s.to_string())
+    }
+}
+
+impl From<String> for Atom {
+    fn from(s: String) -> Atom {
+        Atom(s)
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(fmt, "{}", self.0)
+    }
+}
+
+/// A jump target within a module's instruction stream.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Label(pub u32);
+
+impl Display for Label {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(fmt, "L{}", self.0)
+    }
+}
+
+/// A process identifier.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct Pid(pub u64);
+
+impl Display for Pid {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(fmt, "<0.{}.0>", self.0)
+    }
+}
+
+/// A module/function/arity triple, identifying a callable function.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
+#[repr(C)]
+pub struct MFA {
+    pub module: Atom,
+    pub function: Atom,
+    pub arity: Arity,
+}
+
+impl Display for MFA {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(fmt, "{}:{}/{}", self.module, self.function, self.arity)
+    }
+}
+
+/// A proper Erlang list, as a cons cell chain.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
+#[repr(C)]
+pub enum List {
+    Cons(Box<Value>, Box<List>),
+    Nil,
+}
+
+/// A runtime term. `Tuple` stores its elements as `Value`s so a tuple can
+/// hold registers as well as literals during construction.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
+#[repr(C)]
+pub enum Literal {
+    Integer(BigInt),
+    Atom(String),
+    Pid(Pid),
+    List(List),
+    Tuple(Vec<Value>),
+    Binary(Binary),
+}
+
+impl Literal {
+    /// Erlang's term-ordering category for this term: the standard order is
+    /// `number < atom < reference < fun < port < pid < tuple < map < nil <
+    /// list < bitstring`; collapsed here to the categories this crate
+    /// actually represents.
+    fn term_rank(&self) -> u8 {
+        match self {
+            Literal::Integer(_) => 0,
+            Literal::Atom(_) => 1,
+            Literal::Pid(_) => 2,
+            Literal::Tuple(_) => 3,
+            Literal::List(_) => 4,
+            Literal::Binary(_) => 5,
+        }
+    }
+}
+
+/// Erlang term ordering, not derived structural order: the crate's
+/// declaration order of `Literal`'s variants doesn't match the order BEAM
+/// actually uses (notably, tuples sort before lists), so a plain `#[derive]`
+/// would silently give `ets:match/2`'s `OrderedSet` the wrong range-iteration
+/// order. `term_rank` fixes the cross-type order; same-type terms compare
+/// their contents directly, with tuples ordered by arity before contents as
+/// BEAM does.
+impl PartialOrd for Literal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Literal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Literal::Integer(a), Literal::Integer(b)) => a.cmp(b),
+            (Literal::Atom(a), Literal::Atom(b)) => a.cmp(b),
+            (Literal::Pid(a), Literal::Pid(b)) => a.cmp(b),
+            (Literal::Tuple(a), Literal::Tuple(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+            (Literal::List(a), Literal::List(b)) => a.cmp(b),
+            (Literal::Binary(a), Literal::Binary(b)) => a.cmp(b),
+            _ => self.term_rank().cmp(&other.term_rank()),
+        }
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Literal::Integer(i) => write!(fmt, "{}", i),
+            Literal::Atom(a) => write!(fmt, "{}", a),
+            Literal::Pid(p) => write!(fmt, "{}", p),
+            Literal::List(_) => write!(fmt, "[...]"),
+            Literal::Tuple(elements) => write!(fmt, "{{{}}}", elements.len()),
+            Literal::Binary(b) => write!(fmt, "{}", b),
+        }
+    }
+}
+
+impl From<Literal> for BigInt {
+    fn from(literal: Literal) -> BigInt {
+        match literal {
+            Literal::Integer(i) => i,
+            other => panic!("Can not turn {:?} into an integer", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn term_order_ranks_across_types_the_way_erlang_does() {
+        let number = Literal::Integer(1.into());
+        let atom = Literal::Atom("a".into());
+        let pid = Literal::Pid(Pid(0));
+        let tuple = Literal::Tuple(vec![]);
+        let list = Literal::List(List::Nil);
+        let binary = Literal::Binary(Binary::new());
+
+        let mut terms = vec![
+            binary.clone(),
+            list.clone(),
+            tuple.clone(),
+            pid.clone(),
+            atom.clone(),
+            number.clone(),
+        ];
+        terms.sort();
+
+        assert_eq!(terms, vec![number, atom, pid, tuple, list, binary]);
+    }
+
+    #[test]
+    fn tuples_order_by_arity_before_contents() {
+        let short = Literal::Tuple(vec![Value::Literal(Literal::Integer(9.into()))]);
+        let long = Literal::Tuple(vec![
+            Value::Literal(Literal::Integer(0.into())),
+            Value::Literal(Literal::Integer(0.into())),
+        ]);
+
+        assert!(short < long);
+    }
+}
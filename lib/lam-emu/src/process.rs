@@ -0,0 +1,146 @@
+use super::bytecode::{Register, Value};
+use super::heap::Heap;
+use super::literal::{Literal, Pid, MFA};
+use std::collections::VecDeque;
+
+/// The Global/Local register file for a single process.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    globals: Vec<Value>,
+    locals: Vec<Value>,
+}
+
+impl Registers {
+    fn slot(values: &mut Vec<Value>, index: u32) -> &mut Value {
+        let index = index as usize;
+        if index >= values.len() {
+            values.resize(index + 1, Value::Nil);
+        }
+        &mut values[index]
+    }
+
+    pub fn get(&self, register: &Register) -> Value {
+        match register {
+            Register::Global(i) => self.globals.get(*i as usize).cloned().unwrap_or_default(),
+            Register::Local(i) => self.locals.get(*i as usize).cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn set(&mut self, register: &Register, value: Value) {
+        match register {
+            Register::Global(i) => *Self::slot(&mut self.globals, *i) = value,
+            Register::Local(i) => *Self::slot(&mut self.locals, *i) = value,
+        }
+    }
+
+    /// Zero out local registers beyond the first `keep`, as performed by
+    /// `ClearLocals`.
+    pub fn clear_locals(&mut self, keep: u8) {
+        let keep = keep as usize;
+        for value in self.locals.iter_mut().skip(keep) {
+            *value = Value::Nil;
+        }
+    }
+
+    pub fn shift_locals(&mut self, amount: u8) {
+        let amount = amount as usize;
+        if amount >= self.locals.len() {
+            self.locals.clear();
+        } else {
+            self.locals.drain(0..amount);
+        }
+    }
+}
+
+/// A process mailbox with a scan cursor and a save mark, so a selective
+/// receive (`LoopRec`/`LoopRecEnd`) can resume scanning where a prior mark
+/// left off instead of re-walking already-skipped messages.
+#[derive(Debug, Clone, Default)]
+pub struct Mailbox {
+    messages: VecDeque<Literal>,
+    cursor: usize,
+    mark: usize,
+}
+
+impl Mailbox {
+    pub fn push(&mut self, message: Literal) {
+        self.messages.push_back(message);
+    }
+
+    /// Record the current scan position as the save mark, so a later
+    /// `reset_to_mark` skips every message already known not to match.
+    pub fn mark(&mut self) {
+        self.mark = self.cursor;
+    }
+
+    pub fn reset_to_mark(&mut self) {
+        self.cursor = self.mark.min(self.messages.len());
+    }
+
+    pub fn current(&self) -> Option<&Literal> {
+        self.messages.get(self.cursor)
+    }
+
+    pub fn advance(&mut self) {
+        self.cursor += 1;
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.cursor >= self.messages.len()
+    }
+
+    /// Remove the currently selected message, then rewind the scan back to
+    /// the mark for the next receive.
+    pub fn remove_current(&mut self) {
+        if self.cursor < self.messages.len() {
+            self.messages.remove(self.cursor);
+        }
+        self.reset_to_mark();
+    }
+}
+
+/// Tracks which MFA is executing at each call depth, so `Return` -- which
+/// carries no MFA of its own -- can be attributed to the function that's
+/// actually returning instead of a placeholder.
+#[derive(Debug, Clone, Default)]
+pub struct CallStack {
+    frames: Vec<MFA>,
+}
+
+impl CallStack {
+    pub fn push(&mut self, mfa: MFA) {
+        self.frames.push(mfa);
+    }
+
+    /// A tail call replaces the current frame rather than nesting a new one,
+    /// mirroring `Profiler::tail_enter`: the function being left behind will
+    /// never see its own `Return`.
+    pub fn replace(&mut self, mfa: MFA) {
+        self.frames.pop();
+        self.frames.push(mfa);
+    }
+
+    pub fn pop(&mut self) -> Option<MFA> {
+        self.frames.pop()
+    }
+}
+
+/// Everything the interpreter needs to run a single process: its registers,
+/// its mailbox, its heap, and its call stack.
+#[derive(Debug, Clone, Default)]
+pub struct Process {
+    pub pid: Pid,
+    pub registers: Registers,
+    pub mailbox: Mailbox,
+    pub heap: Heap,
+    pub call_stack: CallStack,
+}
+
+impl Process {
+    pub fn new(pid: Pid) -> Process {
+        Process {
+            pid,
+            ..Process::default()
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use super::literal::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Abstracts the wall clock so the profiler works the same way on a native
+/// runtime (`std::time::Instant`) and on WASM, where `Instant` is
+/// unavailable and time has to come from `web_sys::Performance::now`.
+pub trait Clock {
+    /// Milliseconds since an arbitrary, monotonic epoch.
+    fn now_millis(&self) -> f64;
+}
+
+/// Per-MFA call count and cumulative time, in the style of OTP's
+/// `eprof`/`fprof`. Time is attributed to the function that is returning
+/// when a `Return` is reached, and is inclusive: the caller's timer keeps
+/// running while a callee executes, so a caller's total includes the time
+/// of the functions it calls.
+pub struct Profiler<C: Clock> {
+    clock: C,
+    running: bool,
+    stack: Vec<(MFA, f64)>,
+    totals: HashMap<MFA, ProfileEntry>,
+}
+
+#[derive(Default, Clone)]
+struct ProfileEntry {
+    calls: u64,
+    total_millis: f64,
+}
+
+impl<C: Clock> Profiler<C> {
+    pub fn new(clock: C) -> Profiler<C> {
+        Profiler {
+            clock,
+            running: false,
+            stack: vec![],
+            totals: HashMap::new(),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.totals.clear();
+    }
+
+    /// Called by the interpreter on `Call`, pushing a fresh timer for the
+    /// entered function and bumping its call count.
+    pub fn enter(&mut self, mfa: MFA) {
+        if !self.running {
+            return;
+        }
+        self.totals.entry(mfa.clone()).or_default().calls += 1;
+        self.stack.push((mfa, self.clock.now_millis()));
+    }
+
+    /// Called by the interpreter on `TailCall`. A tail call replaces the
+    /// current stack frame rather than nesting a new one -- there is no
+    /// `Return` coming for the function being left, so its elapsed time is
+    /// charged here instead of in `exit`, and the timer for the replacement
+    /// frame starts fresh. Without this, tail-recursive code would grow the
+    /// stack without bound and misattribute exclusive time to whichever
+    /// frame happened to be on top when profiling stopped.
+    pub fn tail_enter(&mut self, mfa: MFA) {
+        if !self.running {
+            return;
+        }
+        if let Some((prev_mfa, entered_at)) = self.stack.pop() {
+            let elapsed = self.clock.now_millis() - entered_at;
+            self.totals.entry(prev_mfa).or_default().total_millis += elapsed;
+        }
+        self.totals.entry(mfa.clone()).or_default().calls += 1;
+        self.stack.push((mfa, self.clock.now_millis()));
+    }
+
+    /// Called by the interpreter on `Return`, charging the time since entry
+    /// to the function that is returning. The caller's frame is left as it
+    /// is, its timer still counting from its own entry.
+    pub fn exit(&mut self) {
+        if !self.running {
+            return;
+        }
+        if let Some((mfa, entered_at)) = self.stack.pop() {
+            let elapsed = self.clock.now_millis() - entered_at;
+            self.totals.entry(mfa).or_default().total_millis += elapsed;
+        }
+    }
+
+    /// A serde-serializable snapshot, sorted by total time descending so the
+    /// hottest functions come first.
+    pub fn report(&self) -> ProfileReport {
+        let mut entries: Vec<MfaProfile> = self
+            .totals
+            .iter()
+            .map(|(mfa, entry)| MfaProfile {
+                mfa: mfa.clone(),
+                calls: entry.calls,
+                total_millis: entry.total_millis,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.total_millis.total_cmp(&a.total_millis));
+        ProfileReport { entries }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MfaProfile {
+    pub mfa: MFA,
+    pub calls: u64,
+    pub total_millis: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileReport {
+    pub entries: Vec<MfaProfile>,
+}
+
+/// A `Clock` backed by `std::time::Instant`, for native runtimes.
+#[derive(Debug, Clone)]
+pub struct InstantClock {
+    epoch: std::time::Instant,
+}
+
+impl InstantClock {
+    pub fn new() -> InstantClock {
+        InstantClock {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Default for InstantClock {
+    fn default() -> InstantClock {
+        InstantClock::new()
+    }
+}
+
+impl Clock for InstantClock {
+    fn now_millis(&self) -> f64 {
+        self.epoch.elapsed().as_secs_f64() * 1000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeClock {
+        now: Cell<f64>,
+    }
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock { now: Cell::new(0.0) }
+        }
+
+        fn advance(&self, millis: f64) {
+            self.now.set(self.now.get() + millis);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now_millis(&self) -> f64 {
+            self.now.get()
+        }
+    }
+
+    fn mfa(name: &str) -> MFA {
+        MFA {
+            module: Atom::from("m"),
+            function: Atom::from(name),
+            arity: 0,
+        }
+    }
+
+    #[test]
+    fn tail_call_replaces_the_current_frame_instead_of_stacking() {
+        let mut profiler = Profiler::new(FakeClock::new());
+        profiler.start();
+
+        profiler.enter(mfa("loop"));
+        profiler.clock.advance(5.0);
+        profiler.tail_enter(mfa("loop"));
+        profiler.clock.advance(5.0);
+        profiler.tail_enter(mfa("loop"));
+        profiler.clock.advance(5.0);
+        profiler.exit();
+
+        assert!(profiler.stack.is_empty());
+        let report = profiler.report();
+        let loop_entry = report.entries.iter().find(|e| e.mfa.function.as_str() == "loop").unwrap();
+        assert_eq!(loop_entry.calls, 3);
+        assert_eq!(loop_entry.total_millis, 15.0);
+    }
+
+    #[test]
+    fn report_sort_does_not_panic_on_nan_total_millis() {
+        let mut profiler = Profiler::new(FakeClock::new());
+        profiler.start();
+        profiler.enter(mfa("a"));
+        profiler.exit();
+        profiler
+            .totals
+            .get_mut(&mfa("a"))
+            .unwrap()
+            .total_millis = f64::NAN;
+        profiler.enter(mfa("b"));
+        profiler.clock.advance(1.0);
+        profiler.exit();
+
+        let report = profiler.report();
+        assert_eq!(report.entries.len(), 2);
+    }
+}
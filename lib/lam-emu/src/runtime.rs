@@ -0,0 +1,7 @@
+use super::literal::{Literal, MFA};
+
+/// Implemented by a host (native, WASM, ...) to supply native implementations
+/// for `FnCall::BuiltIn` calls the interpreter can't execute itself.
+pub trait Runtime {
+    fn execute(&mut self, mfa: &MFA, args: &[Literal]) -> Literal;
+}
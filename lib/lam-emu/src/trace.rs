@@ -0,0 +1,120 @@
+use super::literal::*;
+
+/// A structured tracing event, emitted by the interpreter at well-defined
+/// points, in the spirit of OTP's `dbg`.
+#[derive(Debug, Clone)]
+pub enum TraceEvent {
+    Call {
+        pid: Pid,
+        mfa: MFA,
+        args: Vec<Literal>,
+    },
+    Return {
+        pid: Pid,
+        mfa: MFA,
+        value: Literal,
+    },
+    Send {
+        from: Pid,
+        to: Pid,
+        message: Literal,
+    },
+    Receive {
+        pid: Pid,
+        message: Literal,
+    },
+    Spawn {
+        parent: Pid,
+        child: Pid,
+        mfa: MFA,
+    },
+    Exit {
+        pid: Pid,
+        reason: Literal,
+    },
+}
+
+/// Which classes of events a process is being traced for, as a per-pid
+/// bitset -- mirrors OTP's `erlang:trace/3` flag list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TraceFlags {
+    pub call: bool,
+    pub send: bool,
+    pub receive_: bool,
+    pub spawn: bool,
+    pub exit: bool,
+}
+
+impl TraceFlags {
+    pub fn none() -> TraceFlags {
+        TraceFlags::default()
+    }
+
+    pub fn all() -> TraceFlags {
+        TraceFlags {
+            call: true,
+            send: true,
+            receive_: true,
+            spawn: true,
+            exit: true,
+        }
+    }
+
+    fn wants(&self, event: &TraceEvent) -> bool {
+        match event {
+            TraceEvent::Call { .. } | TraceEvent::Return { .. } => self.call,
+            TraceEvent::Send { .. } => self.send,
+            TraceEvent::Receive { .. } => self.receive_,
+            TraceEvent::Spawn { .. } => self.spawn,
+            TraceEvent::Exit { .. } => self.exit,
+        }
+    }
+}
+
+fn event_pid(event: &TraceEvent) -> &Pid {
+    match event {
+        TraceEvent::Call { pid, .. } => pid,
+        TraceEvent::Return { pid, .. } => pid,
+        TraceEvent::Send { from, .. } => from,
+        TraceEvent::Receive { pid, .. } => pid,
+        TraceEvent::Spawn { parent, .. } => parent,
+        TraceEvent::Exit { pid, .. } => pid,
+    }
+}
+
+/// Implemented by a runtime to receive trace events the interpreter fires as
+/// it executes `Call`/`TailCall`, `Send`, `PeekMessage`/`RemoveMessage`, and
+/// `Spawn`. The default `emit` drops every event whose class is not enabled
+/// for the firing pid, so `trace` only sees enabled events.
+pub trait Tracer {
+    /// Per-pid flags deciding which event classes actually reach `trace`.
+    fn flags_for(&self, pid: &Pid) -> TraceFlags;
+
+    /// Called for every event whose class is enabled for its pid.
+    fn trace(&mut self, event: TraceEvent);
+
+    /// Entry point the interpreter calls; filters by `flags_for` before
+    /// forwarding to `trace`, so implementors only need to handle `trace`.
+    fn emit(&mut self, event: TraceEvent) {
+        if self.flags_for(event_pid(&event)).wants(&event) {
+            self.trace(event);
+        }
+    }
+}
+
+/// A `Tracer` that collects every emitted event in memory, for use in tests.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingTracer {
+    pub flags: TraceFlags,
+    pub events: Vec<TraceEvent>,
+}
+
+impl Tracer for RecordingTracer {
+    fn flags_for(&self, _pid: &Pid) -> TraceFlags {
+        self.flags
+    }
+
+    fn trace(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+}
@@ -1,7 +1,7 @@
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 
-use lam_emu::{List, Literal, Runtime, Value, MFA};
+use lam_emu::{Clock, List, Literal, Runtime, TraceEvent, TraceFlags, Tracer, Value, MFA};
 use num_bigint::BigInt;
 
 #[wasm_bindgen]
@@ -41,3 +41,50 @@ impl Runtime for WebRuntime {
         }
     }
 }
+
+/// Forwards trace events to the browser console, so `console.log` is the
+/// WASM equivalent of what a native runtime would write to a trace file.
+#[wasm_bindgen]
+#[derive(Default, Debug, Clone)]
+pub struct WebTracer {
+    flags: TraceFlags,
+}
+
+impl Tracer for WebTracer {
+    fn flags_for(&self, _pid: &lam_emu::Pid) -> TraceFlags {
+        self.flags
+    }
+
+    fn trace(&mut self, event: TraceEvent) {
+        console::log_1(&format!("{:?}", event).into());
+    }
+}
+
+/// A `Clock` backed by `web_sys::Performance::now`, since WASM has no
+/// `std::time::Instant`.
+#[derive(Debug, Clone)]
+pub struct WebClock {
+    performance: web_sys::Performance,
+}
+
+impl WebClock {
+    pub fn new() -> WebClock {
+        let performance = web_sys::window()
+            .expect("no global `window` exists")
+            .performance()
+            .expect("performance should be available");
+        WebClock { performance }
+    }
+}
+
+impl Default for WebClock {
+    fn default() -> WebClock {
+        WebClock::new()
+    }
+}
+
+impl Clock for WebClock {
+    fn now_millis(&self) -> f64 {
+        self.performance.now()
+    }
+}